@@ -0,0 +1,138 @@
+//! Bounded caches in front of the RPC client.
+//!
+//! The heal-check tasks poll [`get_block`](RpcCache::get_block) up to 30 times
+//! per stitch across many concurrent spawns, and miner-address resolution
+//! re-parses the same coinbase script pubkeys repeatedly. Both are wrapped in a
+//! fixed-capacity LRU keyed by block hash (capacity tied to `dag_window`) so
+//! overlapping queries hit memory instead of the node, with a hard bound on how
+//! much they can retain under sustained high BPS.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash as StdHash;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use kaspa_addresses::Address;
+use kaspa_consensus_core::block::Block;
+
+/// A minimal fixed-capacity least-recently-used cache.
+struct LruCache<K, V> {
+    capacity: usize,
+    map: HashMap<K, V>,
+    /// Keys ordered least- to most-recently used.
+    order: VecDeque<K>,
+}
+
+impl<K: Eq + StdHash + Clone, V: Clone> LruCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            map: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns a clone of the cached value, marking the key most-recently used.
+    fn get(&mut self, key: &K) -> Option<V> {
+        let value = self.map.get(key)?.clone();
+        self.touch(key);
+        Some(value)
+    }
+
+    /// Inserts `value`, evicting the least-recently-used entry when at capacity.
+    fn put(&mut self, key: K, value: V) {
+        if self.map.insert(key.clone(), value).is_some() {
+            self.touch(&key);
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(old) = self.order.pop_front() {
+                self.map.remove(&old);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &K) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(key.clone());
+    }
+}
+
+/// Shared, bounded cache wrapping block and miner-address RPC lookups.
+pub struct RpcCache {
+    rpc: kaspa_rpc_core::client::RpcClient,
+    blocks: Mutex<LruCache<String, Block>>,
+    addresses: Mutex<LruCache<String, Address>>,
+}
+
+impl RpcCache {
+    /// Creates a cache over `rpc` sized to `capacity` (typically `dag_window`).
+    /// The address cache is also keyed by block hash and is kept smaller, since
+    /// miners are resolved only for blocks whose heal confirmed, far fewer than
+    /// the blocks fetched.
+    pub fn new(rpc: kaspa_rpc_core::client::RpcClient, capacity: usize) -> Self {
+        Self {
+            rpc,
+            blocks: Mutex::new(LruCache::new(capacity)),
+            addresses: Mutex::new(LruCache::new((capacity / 8).max(16))),
+        }
+    }
+
+    /// Fetches a block, serving it from cache when it has been seen recently.
+    pub async fn get_block(&self, hash: &kaspa_hashes::Hash) -> Result<Block> {
+        let key = hash.to_string();
+        if let Some(block) = self.blocks.lock().unwrap().get(&key) {
+            return Ok(block);
+        }
+        let block = self.rpc.get_block(hash).await?;
+        self.blocks.lock().unwrap().put(key, block.clone());
+        Ok(block)
+    }
+
+    /// Resolves (and caches) the miner address for `block`, avoiding repeated
+    /// script-pubkey parsing for blocks queried by many watchers.
+    pub fn miner_address(&self, block: &Block) -> Option<Address> {
+        let key = block.hash().to_string();
+        if let Some(addr) = self.addresses.lock().unwrap().get(&key) {
+            return Some(addr);
+        }
+        let addr = crate::get_miner_address(block)?;
+        self.addresses.lock().unwrap().put(key, addr.clone());
+        Some(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut lru: LruCache<u32, u32> = LruCache::new(2);
+        lru.put(1, 10);
+        lru.put(2, 20);
+
+        // Touch key 1 so key 2 becomes the least-recently used.
+        assert_eq!(lru.get(&1), Some(10));
+        lru.put(3, 30);
+
+        assert_eq!(lru.get(&2), None, "the least-recently-used key is evicted");
+        assert_eq!(lru.get(&1), Some(10));
+        assert_eq!(lru.get(&3), Some(30));
+    }
+
+    #[test]
+    fn reinsert_updates_value_without_growing() {
+        let mut lru: LruCache<u32, u32> = LruCache::new(2);
+        lru.put(1, 10);
+        lru.put(1, 11);
+        lru.put(2, 20);
+
+        // The re-inserted key must not have consumed a second slot.
+        assert_eq!(lru.get(&1), Some(11));
+        assert_eq!(lru.get(&2), Some(20));
+    }
+}
@@ -12,6 +12,26 @@ pub struct Config {
     pub max_reward_sompi: u64,
     pub min_rate_limit: u64,
     pub dag_window: usize,
+    /// Use sampled-Brandes approximate betweenness instead of the exact Θ(V·E) computation.
+    #[serde(default)]
+    pub approx_centrality: bool,
+    /// Number of pivot sources to sample when `approx_centrality` is set.
+    #[serde(default = "default_centrality_samples")]
+    pub centrality_samples: usize,
+    /// Path to persist/restore the rolling DAG snapshot; `None` disables persistence.
+    pub snapshot_path: Option<String>,
+    /// Sample from the top-N fracture candidates (>1 enables diversity mode; 0/1 stays deterministic).
+    #[serde(default)]
+    pub diversity_top_n: usize,
+    /// Seed for the diversity-mode RNG, so operators can make each instance diverge.
+    #[serde(default)]
+    pub diversity_seed: u64,
+}
+
+/// Default pivot-sample count when `approx_centrality` is enabled without an
+/// explicit `centrality_samples`.
+fn default_centrality_samples() -> usize {
+    50
 }
 
 impl Config {
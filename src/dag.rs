@@ -1,8 +1,12 @@
 use petgraph::{Graph, Directed, graph::NodeIndex};
 use kaspa_consensus_core::block::Block;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
 use anyhow::Result;
 
+use crate::rng::SeededRng;
+use crate::storage::{BlockStorage, Snapshot};
+
 pub type Dag = Graph<BlockInfo, (), Directed>;
 
 /// Contains essential information about a block in the DAG.
@@ -14,12 +18,26 @@ pub struct BlockInfo {
     pub timestamp: u64,
 }
 
+/// A stitch whose heal check has not yet confirmed, tracked so it can be
+/// resumed after a restart instead of dropping a reward already committed to.
+#[derive(Clone, Debug)]
+pub struct PendingStitch {
+    pub block_hash: String,
+    pub tips: Vec<String>,
+    pub reward: u64,
+    /// Heal-check budget for the watcher. This is not decremented as iterations
+    /// are consumed, so a resumed stitch restarts from the full budget rather
+    /// than picking up where it left off.
+    pub heal_iterations: u32,
+}
+
 /// A rolling DAG with fixed capacity. When at capacity, the oldest blocks are evicted.
 pub struct RollingDag {
     graph: Dag,
     idx: HashMap<String, NodeIndex>,
     order: VecDeque<String>,
     capacity: usize,
+    pending: Vec<PendingStitch>,
 }
 
 impl RollingDag {
@@ -30,6 +48,7 @@ impl RollingDag {
             idx: HashMap::new(),
             order: VecDeque::with_capacity(capacity),
             capacity,
+            pending: Vec::new(),
         }
     }
 
@@ -43,11 +62,20 @@ impl RollingDag {
         }
 
         let info = BlockInfo {
-            hash: hash.clone(),
+            hash,
             blue_score: block.header.blue_score,
             parents: block.header.direct_parents.iter().map(|h| h.to_string()).collect(),
             timestamp: block.header.timestamp,
         };
+        self.insert_info(info);
+        true
+    }
+
+    /// Inserts an already-built [`BlockInfo`], evicting the oldest node when at
+    /// capacity and wiring edges to parents already in the window. Shared by
+    /// live ingestion and snapshot restore.
+    fn insert_info(&mut self, info: BlockInfo) {
+        let hash = info.hash.clone();
 
         // Evict oldest
         if self.order.len() >= self.capacity {
@@ -69,15 +97,147 @@ impl RollingDag {
                 self.graph.add_edge(p_node, node, ());
             }
         }
-        true
+    }
+
+    /// Number of blocks currently held in the window.
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Records a stitch whose heal check is still outstanding so it survives a restart.
+    pub fn register_stitch(&mut self, stitch: PendingStitch) {
+        self.pending.retain(|s| s.block_hash != stitch.block_hash);
+        self.pending.push(stitch);
+    }
+
+    /// Drops the pending stitch for `block_hash`, e.g. once its heal confirmed.
+    pub fn remove_stitch(&mut self, block_hash: &str) {
+        self.pending.retain(|s| s.block_hash != block_hash);
+    }
+
+    /// The stitches whose heal checks have not yet confirmed.
+    pub fn pending_stitches(&self) -> &[PendingStitch] {
+        &self.pending
+    }
+
+    /// Serializes the window (in insertion order) and its pending stitches to `path`.
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<()> {
+        let blocks: Vec<BlockInfo> = self
+            .order
+            .iter()
+            .filter_map(|h| self.idx.get(h).map(|&n| self.graph[n].clone()))
+            .collect();
+        let snapshot = Snapshot {
+            blocks,
+            pending: self.pending.clone(),
+        };
+        BlockStorage::save(path.as_ref(), &snapshot)
+    }
+
+    /// Rebuilds a [`RollingDag`] of the given `capacity` from a snapshot on disk,
+    /// replaying blocks in their persisted insertion order.
+    pub fn load_snapshot(path: impl AsRef<Path>, capacity: usize) -> Result<Self> {
+        let snapshot = BlockStorage::load(path.as_ref())?;
+        let mut dag = Self::new(capacity);
+        for info in snapshot.blocks {
+            dag.insert_info(info);
+        }
+        dag.pending = snapshot.pending;
+        Ok(dag)
     }
 
     /// Finds a "fracture" point in the DAG where
     /// a node has at least two children whose blue score delta is >= min_delta,
     /// and prioritizes by betweenness centrality.
-    pub fn find_fracture(&self, min_delta: u64) -> Option<(NodeIndex, Vec<NodeIndex>)> {
+    /// Centrality is scaled by each candidate's merge depth (see `ranked_candidates`).
+    pub fn find_fracture(&self, min_delta: u64) -> Option<(NodeIndex, Vec<NodeIndex>, f64, usize)> {
         use petgraph::algo::betweenness_centrality;
         let betweenness = betweenness_centrality(&self.graph);
+        let ranked = self.ranked_candidates(min_delta, &betweenness);
+        ranked.first().map(|c| self.to_fracture(c))
+    }
+
+    /// Like [`find_fracture`], but estimates betweenness centrality from `samples`
+    /// pivot sources instead of computing it exactly over all `V` nodes.
+    ///
+    /// Exact betweenness is Θ(V·E); on a window of thousands of blocks that cost
+    /// dominates every `BlockAdded`. Sampling `k` pivots drops it to roughly
+    /// O(E·k) while keeping the fracture ranking stable, since the estimate is
+    /// scaled back up by `V/k` to remain unbiased.
+    ///
+    /// Note this only bounds the centrality term. Ranking still reconstructs the
+    /// selected chain and computes each candidate's merge depth; those traversals
+    /// are memoized by the node they start from (see [`ranked_candidates`]), and
+    /// each one is O(V + E), so that part of the cost grows with the number of
+    /// candidates and distinct children rather than with `k`.
+    ///
+    /// [`find_fracture`]: RollingDag::find_fracture
+    /// [`ranked_candidates`]: RollingDag::ranked_candidates
+    pub fn find_fracture_approx(&self, min_delta: u64, samples: usize) -> Option<(NodeIndex, Vec<NodeIndex>, f64, usize)> {
+        let betweenness = self.approx_betweenness(samples);
+        let ranked = self.ranked_candidates(min_delta, &betweenness);
+        ranked.first().map(|c| self.to_fracture(c))
+    }
+
+    /// Like [`find_fracture`], but instead of always taking the top candidate,
+    /// samples from the top `top_n` with probability proportional to their
+    /// combined centrality × merge-depth / delta score using `rng`.
+    ///
+    /// Set `samples` to `Some(k)` to use the approximate centrality mode. When
+    /// many instances run the same network this spreads healing across distinct
+    /// weak points rather than every node dog-piling the single strongest one.
+    ///
+    /// [`find_fracture`]: RollingDag::find_fracture
+    pub fn find_fracture_diverse(
+        &self,
+        min_delta: u64,
+        samples: Option<usize>,
+        top_n: usize,
+        rng: &mut SeededRng,
+    ) -> Option<(NodeIndex, Vec<NodeIndex>, f64, usize)> {
+        let betweenness = match samples {
+            Some(k) => self.approx_betweenness(k),
+            None => petgraph::algo::betweenness_centrality(&self.graph),
+        };
+        let ranked = self.ranked_candidates(min_delta, &betweenness);
+        if ranked.is_empty() {
+            return None;
+        }
+
+        let pool = &ranked[..top_n.clamp(1, ranked.len())];
+        // Sampling weight mirrors the ranking score (centrality × merge depth),
+        // divided by (1 + blue-score delta) so that, as in the ranking's
+        // tie-break, candidates with a larger delta are picked less often.
+        let weights: Vec<f64> = pool
+            .iter()
+            .map(|c| Self::rank_score(c) / (1.0 + c.2 as f64))
+            .collect();
+        let pick = rng.weighted_index(&weights).unwrap_or(0);
+        Some(self.to_fracture(&pool[pick]))
+    }
+
+    /// Builds the fracture candidate list for `min_delta`, ranked best-first.
+    ///
+    /// Each entry is `(node, betweenness, delta, merge_depth)`, sorted best-first
+    /// by a combined score that multiplies centrality by merge depth — a deep,
+    /// long-lived split on a central node outranks a shallow transient one — with
+    /// a smaller blue-score delta breaking ties. Depth is a primary factor rather
+    /// than a tie-break, since an `f64` centrality almost never ties exactly and
+    /// would otherwise shadow it entirely.
+    ///
+    /// The selected chain is reconstructed once, and the merge-depth traversals
+    /// (descendant reach sets and nearest-ancestor positions) are memoized by
+    /// their start node across candidates, so a child shared by several
+    /// candidates is walked once; descendants shared by different children are
+    /// still walked once per child. Even so, ranking is the one remaining per-`BlockAdded` cost that the
+    /// sampled-centrality mode does **not** remove — see [`find_fracture_approx`].
+    ///
+    /// [`find_fracture_approx`]: RollingDag::find_fracture_approx
+    fn ranked_candidates(&self, min_delta: u64, betweenness: &[f64]) -> Vec<(NodeIndex, f64, u64, usize)> {
+        let chain = self.selected_chain_positions();
+        let tip_pos = chain.values().copied().max().unwrap_or(0);
+        let mut reach_memo: HashMap<NodeIndex, HashSet<usize>> = HashMap::new();
+        let mut ancestor_memo: HashMap<NodeIndex, usize> = HashMap::new();
         let mut candidates = vec![];
 
         for node in self.graph.node_indices() {
@@ -92,17 +252,317 @@ impl RollingDag {
             }
             if delta < min_delta { continue; }
 
-            candidates.push((node, betweenness[node.index()], delta));
+            let depth = self.merge_depth(node, &children, &chain, tip_pos, &mut reach_memo, &mut ancestor_memo);
+            candidates.push((node, betweenness[node.index()], delta, depth));
         }
 
-        // Sort by betweenness descending (higher is better), then by delta ascending (lower is better)
         candidates.sort_by(|a, b| {
-            // Sort: betweenness descending, then delta ascending
-            b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
+            Self::rank_score(b).partial_cmp(&Self::rank_score(a)).unwrap_or(std::cmp::Ordering::Equal)
                 .then(a.2.cmp(&b.2))
         });
-        let best = candidates.first()?;
-        let tips: Vec<_> = self.graph.neighbors_directed(best.0, petgraph::Direction::Outgoing).collect();
-        Some((best.0, tips))
+        candidates
+    }
+
+    /// Combined fracture priority: centrality scaled by merge depth, so a fracture
+    /// that splits the chain deeply before re-merging outranks an equally central
+    /// but shallow one. Shared by the deterministic and diversity-mode finders.
+    fn rank_score(candidate: &(NodeIndex, f64, u64, usize)) -> f64 {
+        let &(_, betweenness, _, depth) = candidate;
+        (betweenness.max(0.0) + 1.0) * (1.0 + depth as f64)
+    }
+
+    /// Expands a ranked candidate into the `(node, tips, betweenness, depth)`
+    /// fracture tuple returned by the public finders.
+    fn to_fracture(&self, candidate: &(NodeIndex, f64, u64, usize)) -> (NodeIndex, Vec<NodeIndex>, f64, usize) {
+        let &(node, betweenness, _, depth) = candidate;
+        let tips: Vec<_> = self.graph.neighbors_directed(node, petgraph::Direction::Outgoing).collect();
+        (node, tips, betweenness, depth)
+    }
+
+    /// Reconstructs the GHOSTDAG selected chain within the window, as an ordered
+    /// list of nodes from the selected tip back toward the oldest reachable block.
+    ///
+    /// The selected tip is the block with the highest blue score (ties broken by
+    /// hash); from any block the selected parent is likewise its highest-blue-score
+    /// parent still present in the window.
+    fn selected_chain(&self) -> Vec<NodeIndex> {
+        let best = |a: &NodeIndex, b: &NodeIndex| {
+            let (ia, ib) = (&self.graph[*a], &self.graph[*b]);
+            ia.blue_score.cmp(&ib.blue_score).then_with(|| ia.hash.cmp(&ib.hash))
+        };
+
+        let mut chain = Vec::new();
+        let mut cur = match self.graph.node_indices().max_by(best) {
+            Some(tip) => tip,
+            None => return chain,
+        };
+        loop {
+            chain.push(cur);
+            match self.graph.neighbors_directed(cur, petgraph::Direction::Incoming).max_by(best) {
+                Some(parent) => cur = parent,
+                None => break,
+            }
+        }
+        chain
+    }
+
+    /// Maps each selected-chain node to its position, increasing from the oldest
+    /// reachable block (0) up to the selected tip.
+    fn selected_chain_positions(&self) -> HashMap<NodeIndex, usize> {
+        let chain = self.selected_chain();
+        let len = chain.len();
+        // `chain` is tip-first; flip so higher positions sit closer to the tip.
+        chain.into_iter().enumerate().map(|(i, n)| (n, len - 1 - i)).collect()
+    }
+
+    /// Computes the merge depth of a fracture candidate: the number of
+    /// selected-chain blocks between the weak node and the point where its
+    /// divergent children re-merge, or the distance to the window edge (the
+    /// selected tip) if they never re-merge within the window.
+    fn merge_depth(
+        &self,
+        weak: NodeIndex,
+        children: &[NodeIndex],
+        chain: &HashMap<NodeIndex, usize>,
+        tip_pos: usize,
+        reach_memo: &mut HashMap<NodeIndex, HashSet<usize>>,
+        ancestor_memo: &mut HashMap<NodeIndex, usize>,
+    ) -> usize {
+        let base = self.nearest_chain_pos(weak, chain, ancestor_memo);
+
+        // For each divergent child, the selected-chain positions its subtree reaches.
+        let mut reach_count: HashMap<usize, usize> = HashMap::new();
+        for &child in children {
+            for &pos in self.reachable_chain_positions(child, chain, reach_memo).iter() {
+                if pos > base {
+                    *reach_count.entry(pos).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Re-merge = the earliest chain position reachable from at least two children.
+        let remerge = reach_count
+            .iter()
+            .filter(|&(_, &count)| count >= 2)
+            .map(|(&pos, _)| pos)
+            .min();
+
+        match remerge {
+            Some(pos) => pos - base,
+            None => tip_pos.saturating_sub(base),
+        }
+    }
+
+    /// Position on the selected chain of `node` itself, or of its nearest
+    /// selected-chain ancestor; 0 if none is reachable. `memo` caches the result
+    /// per node so repeated candidates don't re-walk the same ancestry.
+    fn nearest_chain_pos(
+        &self,
+        node: NodeIndex,
+        chain: &HashMap<NodeIndex, usize>,
+        memo: &mut HashMap<NodeIndex, usize>,
+    ) -> usize {
+        if let Some(&pos) = chain.get(&node) {
+            return pos;
+        }
+        if let Some(&pos) = memo.get(&node) {
+            return pos;
+        }
+        // Walk ancestors breadth-first until we land on the chain.
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(node);
+        seen.insert(node);
+        let mut best = 0;
+        while let Some(cur) = queue.pop_front() {
+            for parent in self.graph.neighbors_directed(cur, petgraph::Direction::Incoming) {
+                if let Some(&pos) = chain.get(&parent) {
+                    best = best.max(pos);
+                } else if seen.insert(parent) {
+                    queue.push_back(parent);
+                }
+            }
+        }
+        memo.insert(node, best);
+        best
+    }
+
+    /// The set of selected-chain positions reachable by following descendants of
+    /// `from`. `memo` caches the set per node so a subtree shared by several
+    /// candidates is traversed only once per ranking pass.
+    fn reachable_chain_positions<'a>(
+        &self,
+        from: NodeIndex,
+        chain: &HashMap<NodeIndex, usize>,
+        memo: &'a mut HashMap<NodeIndex, HashSet<usize>>,
+    ) -> &'a HashSet<usize> {
+        if !memo.contains_key(&from) {
+            let mut hits = HashSet::new();
+            let mut seen = HashSet::new();
+            let mut queue = VecDeque::new();
+            queue.push_back(from);
+            seen.insert(from);
+            while let Some(cur) = queue.pop_front() {
+                if let Some(&pos) = chain.get(&cur) {
+                    hits.insert(pos);
+                }
+                for child in self.graph.neighbors_directed(cur, petgraph::Direction::Outgoing) {
+                    if seen.insert(child) {
+                        queue.push_back(child);
+                    }
+                }
+            }
+            memo.insert(from, hits);
+        }
+        &memo[&from]
+    }
+
+    /// Returns whether `block` lies on the reconstructed selected chain.
+    pub fn is_in_selected_chain(&self, block: &Block) -> bool {
+        let hash = block.hash().to_string();
+        self.selected_chain().iter().any(|&n| self.graph[n].hash == hash)
+    }
+
+    /// Estimates betweenness centrality with the Brandes algorithm run from a
+    /// deterministic, evenly-spaced sample of `samples` pivot sources.
+    ///
+    /// Returns a vector indexed by `NodeIndex::index()`; the raw accumulated
+    /// dependencies are scaled by `V / k` so the magnitudes match a full run.
+    fn approx_betweenness(&self, samples: usize) -> Vec<f64> {
+        use petgraph::Direction;
+
+        let n = self.graph.node_bound();
+        let mut centrality = vec![0.0f64; n];
+        let nodes: Vec<NodeIndex> = self.graph.node_indices().collect();
+        let v = nodes.len();
+        if v == 0 {
+            return centrality;
+        }
+
+        let k = samples.clamp(1, v);
+        // Strided pivot selection spreads the sample across the insertion order.
+        let stride = (v / k).max(1);
+        let mut pivots = Vec::with_capacity(k);
+        let mut i = 0;
+        while pivots.len() < k && i < v {
+            pivots.push(nodes[i]);
+            i += stride;
+        }
+
+        for &s in &pivots {
+            // Single-source Brandes accumulation over the unweighted DAG.
+            let mut sigma = vec![0.0f64; n];
+            let mut dist = vec![-1i64; n];
+            let mut preds: Vec<Vec<NodeIndex>> = vec![Vec::new(); n];
+            let mut stack: Vec<NodeIndex> = Vec::new();
+            let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+
+            sigma[s.index()] = 1.0;
+            dist[s.index()] = 0;
+            queue.push_back(s);
+
+            while let Some(cur) = queue.pop_front() {
+                stack.push(cur);
+                for w in self.graph.neighbors_directed(cur, Direction::Outgoing) {
+                    if dist[w.index()] < 0 {
+                        dist[w.index()] = dist[cur.index()] + 1;
+                        queue.push_back(w);
+                    }
+                    if dist[w.index()] == dist[cur.index()] + 1 {
+                        sigma[w.index()] += sigma[cur.index()];
+                        preds[w.index()].push(cur);
+                    }
+                }
+            }
+
+            // Back-propagate dependencies in reverse BFS order.
+            let mut delta = vec![0.0f64; n];
+            while let Some(w) = stack.pop() {
+                let dw = delta[w.index()];
+                let sw = sigma[w.index()];
+                for &p in &preds[w.index()] {
+                    delta[p.index()] += (sigma[p.index()] / sw) * (1.0 + dw);
+                }
+                if w != s {
+                    centrality[w.index()] += delta[w.index()];
+                }
+            }
+        }
+
+        // Rescale the sampled estimate to the full-V magnitude.
+        let scale = v as f64 / k as f64;
+        for c in centrality.iter_mut() {
+            *c *= scale;
+        }
+        centrality
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a `BlockInfo` with the given hash, blue score, and parent hashes.
+    fn info(hash: &str, blue_score: u64, parents: &[&str]) -> BlockInfo {
+        BlockInfo {
+            hash: hash.to_string(),
+            blue_score,
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            timestamp: blue_score,
+        }
+    }
+
+    /// Assembles a RollingDag from blocks given in insertion (topological) order.
+    fn dag_from(blocks: &[BlockInfo]) -> RollingDag {
+        let mut dag = RollingDag::new(64);
+        for b in blocks {
+            dag.insert_info(b.clone());
+        }
+        dag
+    }
+
+    #[test]
+    fn merge_depth_counts_chain_blocks_to_remerge() {
+        // g -> a ; a -> b, a -> c (the fracture) ; b,c -> d (re-merge).
+        let dag = dag_from(&[
+            info("g", 0, &[]),
+            info("a", 1, &["g"]),
+            info("b", 2, &["a"]),
+            info("c", 2, &["a"]),
+            info("d", 3, &["b", "c"]),
+        ]);
+
+        let betweenness = vec![0.0; dag.graph.node_bound()];
+        let ranked = dag.ranked_candidates(0, &betweenness);
+
+        // `a` is the only node with two children; its split re-merges at `d`,
+        // two selected-chain positions above `a` regardless of the hash tie-break.
+        assert_eq!(ranked.len(), 1);
+        let (node, _, _, depth) = ranked[0];
+        assert_eq!(dag.graph[node].hash, "a");
+        assert_eq!(depth, 2);
+    }
+
+    #[test]
+    fn approx_betweenness_peaks_in_the_middle() {
+        // A straight chain a -> b -> c -> d -> e: the interior nodes lie on the
+        // most shortest paths, so centrality should peak at the centre and be
+        // zero at the endpoints.
+        let dag = dag_from(&[
+            info("a", 0, &[]),
+            info("b", 1, &["a"]),
+            info("c", 2, &["b"]),
+            info("d", 3, &["c"]),
+            info("e", 4, &["d"]),
+        ]);
+
+        // Full sampling (k == V) reproduces exact betweenness.
+        let centrality = dag.approx_betweenness(dag.node_count());
+        let at = |h: &str| centrality[dag.idx[h].index()];
+
+        assert!(at("c") > at("b"));
+        assert!(at("b") > at("a"));
+        assert_eq!(at("a"), 0.0);
+        assert_eq!(at("e"), 0.0);
     }
 }
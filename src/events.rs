@@ -0,0 +1,79 @@
+//! Structured lifecycle events for the DAG/stitch pipeline.
+//!
+//! Each lifecycle `info!`/`warn!` site in the main loop and the heal watchers
+//! (block added, orphan, fracture, stitch, heal, failed reward) additionally
+//! emits one of these events over an optional [`tokio::sync::mpsc`] channel owned by `main`,
+//! letting downstream consumers build metrics exporters, dashboards, or test
+//! harnesses that assert on the exact healing sequence instead of scraping logs.
+//! Snapshot and settled-marker persistence warnings emit no event.
+//!
+//! The whole subsystem is gated behind the `events` feature. When it is off the
+//! [`emit_event!`] macro expands to nothing, so there is no runtime overhead.
+
+use chrono::Utc;
+use tokio::sync::mpsc;
+
+/// A lifecycle event paired with the microsecond UNIX timestamp at which it was emitted.
+#[derive(Clone, Debug)]
+pub struct TimedEvent {
+    /// Microseconds since the UNIX epoch at emission time.
+    pub timestamp_us: i64,
+    pub event: NodeEvent,
+}
+
+/// A single event in the DAG/stitch lifecycle.
+#[derive(Clone, Debug)]
+pub enum NodeEvent {
+    BlockAdded { hash: String, blue_score: u64 },
+    OrphanDetected { hash: String },
+    FractureFound { weak_hash: String, delta: u64, betweenness: f64, merge_depth: usize },
+    StitchBroadcast { reward: u64, tips: Vec<String> },
+    HealConfirmed { txid: String },
+    RewardFailed,
+}
+
+/// Cloneable handle that forwards events onto the channel owned by `main`.
+///
+/// A bus built with [`EventBus::disabled`] drops everything; the usual path is
+/// [`EventBus::channel`], whose receiver `main` drains or hands to a consumer.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    tx: Option<mpsc::UnboundedSender<TimedEvent>>,
+}
+
+impl EventBus {
+    /// Creates a live bus and the receiver that drains it.
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<TimedEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx: Some(tx) }, rx)
+    }
+
+    /// Creates a bus that silently drops every event.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Stamps `event` with the current time and sends it; a no-op on a disabled
+    /// bus or once the receiver has been dropped.
+    pub fn emit(&self, event: NodeEvent) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(TimedEvent {
+                timestamp_us: Utc::now().timestamp_micros(),
+                event,
+            });
+        }
+    }
+}
+
+/// Emits a [`NodeEvent`] on `$bus`, compiled out entirely unless the `events`
+/// feature is enabled so callers pay nothing (not even argument evaluation)
+/// when the subsystem is off.
+#[macro_export]
+macro_rules! emit_event {
+    ($bus:expr, $event:expr) => {{
+        #[cfg(feature = "events")]
+        {
+            $bus.emit($event);
+        }
+    }};
+}
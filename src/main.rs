@@ -1,5 +1,9 @@
+mod cache;
 mod config;
 mod dag;
+mod events;
+mod rng;
+mod storage;
 mod secure_wallet;
 mod p2p_stitch;
 mod adaptive;
@@ -7,12 +11,23 @@ mod adaptive;
 use anyhow::Result;
 use kaspa_addresses::Address;
 use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
 use chrono::Utc;
 use log::{info, warn};
 
+use events::EventBus;
+// `NodeEvent` is only ever named inside `emit_event!`, which expands to nothing
+// unless the `events` feature is on, so the import would be unused otherwise.
+#[cfg(feature = "events")]
+use events::NodeEvent;
+
 /// Interval for healing check in seconds.
 const HEAL_CHECK_INTERVAL_SECS: u64 = 2;
 
+/// Number of heal-check iterations attempted per stitch before giving up.
+const HEAL_CHECK_ITERATIONS: u32 = 30;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
@@ -23,15 +38,101 @@ async fn main() -> Result<()> {
     let mut wallet = secure_wallet::load_or_create_wallet(&rpc_http_url).await?;
     let sk = wallet.private_key().clone();
 
-    let mut rolling_dag = dag::RollingDag::new(cfg.dag_window);
+    // Optional structured-event channel; drained by a downstream consumer when
+    // the `events` feature is on, a no-op bus otherwise. The receiver is handed
+    // to a background task so the unbounded channel can't accumulate events for
+    // the lifetime of the process.
+    #[cfg(feature = "events")]
+    let event_bus = {
+        let (bus, mut event_rx) = events::EventBus::channel();
+        tokio::spawn(async move {
+            while let Some(timed) = event_rx.recv().await {
+                log::debug!("event @{}us: {:?}", timed.timestamp_us, timed.event);
+            }
+        });
+        bus
+    };
+    #[cfg(not(feature = "events"))]
+    let event_bus = events::EventBus::disabled();
+
+    // Restore the persisted window (and any in-flight stitches) if we have one,
+    // so a crash doesn't drop rewards the bot already committed to.
+    let mut rolling_dag = match cfg.snapshot_path.as_deref() {
+        Some(path) => match dag::RollingDag::load_snapshot(path, cfg.dag_window) {
+            Ok(restored) => {
+                info!(
+                    "Restored snapshot: {} blocks, {} pending stitches",
+                    restored.node_count(),
+                    restored.pending_stitches().len()
+                );
+                restored
+            }
+            Err(e) => {
+                warn!("No snapshot restored ({:?}); starting fresh", e);
+                dag::RollingDag::new(cfg.dag_window)
+            }
+        },
+        None => dag::RollingDag::new(cfg.dag_window),
+    };
+
     let rpc_http = kaspa_rpc_core::client::RpcClient::new(&rpc_http_url)?;
+
+    // Bounded caches shared across the fracture detector and all heal watchers,
+    // sized to the rolling window so overlapping recent-block queries stay cheap.
+    let rpc_cache = Arc::new(cache::RpcCache::new(rpc_http.clone(), cfg.dag_window));
+
+    // Heal watchers report the block hash of every stitch that reaches a terminal
+    // state (reward paid, reward failed, or budget exhausted) so the main loop can clear it from
+    // the snapshot. Without this a confirmed stitch lingers as pending and gets
+    // re-resumed — and re-paid — on the next restart.
+    let (heal_done_tx, mut heal_done_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+    // Sidecar log of stitches whose reward was submitted; watchers append to it
+    // before paying so resume can't pay the same stitch twice after a crash.
+    let settled_path = cfg.snapshot_path.as_ref().map(|p| format!("{}.settled", p));
+    let settled = settled_path
+        .as_deref()
+        .map(|p| storage::BlockStorage::load_settled(std::path::Path::new(p)))
+        .unwrap_or_default();
+
+    // Resume heal checks for stitches that hadn't confirmed before the restart,
+    // dropping any already marked settled so their reward is never re-sent.
+    let mut pruned_settled = false;
+    for stitch in rolling_dag.pending_stitches().to_vec() {
+        if settled.contains(&stitch.block_hash) {
+            rolling_dag.remove_stitch(&stitch.block_hash);
+            pruned_settled = true;
+            continue;
+        }
+        if let Ok(block_hash) = kaspa_hashes::Hash::from_str(&stitch.block_hash) {
+            spawn_heal_watcher(
+                rpc_cache.clone(),
+                wallet.clone(),
+                event_bus.clone(),
+                heal_done_tx.clone(),
+                settled_path.clone(),
+                block_hash,
+                stitch.tips.iter().cloned().collect(),
+                stitch.reward,
+                stitch.heal_iterations,
+            );
+        }
+    }
+    if pruned_settled {
+        if let Some(path) = cfg.snapshot_path.as_deref() {
+            if let Err(e) = rolling_dag.save_snapshot(path) {
+                warn!("Failed to save snapshot: {:?}", e);
+            }
+        }
+    }
+
     let tips = rpc_http.get_tip_hashes().await?;
     for hash in tips.iter().rev().take(cfg.dag_window) {
-        if let Ok(block) = rpc_http.get_block(hash).await {
+        if let Ok(block) = rpc_cache.get_block(hash).await {
             rolling_dag.add_block(block);
         }
     }
-    info!("DAG ready: {} blocks", rolling_dag.graph.node_count());
+    info!("DAG ready: {} blocks", rolling_dag.node_count());
 
     let p2p_adaptor = p2p_stitch::setup_p2p(&cfg).await?;
     let mut block_stream = kaspa_rpc_core::notifier::Notifier::new(rpc_http.clone()).await?.start().await?;
@@ -39,8 +140,28 @@ async fn main() -> Result<()> {
     let mut adaptive_engine = cfg.adaptive.then(|| adaptive::AdaptiveEngine::new(cfg.clone()));
     let mut block_times = VecDeque::with_capacity(100);
 
+    // Diversity mode: sample among the top candidates / tips with a seeded RNG so
+    // independent instances spread their healing instead of colliding.
+    let diversity = cfg.diversity_top_n > 1;
+    let mut fracture_rng = rng::SeededRng::new(cfg.diversity_seed);
+
     while let Ok(notification) = block_stream.recv().await {
         if let kaspa_rpc_core::Notification::BlockAdded(block) = notification {
+            // Clear any stitches whose watchers reached a terminal state and
+            // persist the pruned set, so resume stays idempotent across restarts.
+            let mut pruned = false;
+            while let Ok(done) = heal_done_rx.try_recv() {
+                rolling_dag.remove_stitch(&done);
+                pruned = true;
+            }
+            if pruned {
+                if let Some(path) = cfg.snapshot_path.as_deref() {
+                    if let Err(e) = rolling_dag.save_snapshot(path) {
+                        warn!("Failed to save snapshot: {:?}", e);
+                    }
+                }
+            }
+
             let now_ms = Utc::now().timestamp_millis();
             block_times.push_back(now_ms);
             if block_times.len() > 100 { block_times.pop_front(); }
@@ -56,10 +177,17 @@ async fn main() -> Result<()> {
             } else { 1.0 };
 
             info!("Block: {} (blue={}) | BPS: {:.1}", block.hash(), block.header.blue_score, bps);
+            emit_event!(event_bus, NodeEvent::BlockAdded {
+                hash: block.hash().to_string(),
+                blue_score: block.header.blue_score,
+            });
             rolling_dag.add_block(block.clone());
 
             let is_orphan = !rolling_dag.is_in_selected_chain(&block);
-            if is_orphan { info!("ORPHAN: {}", block.hash()); }
+            if is_orphan {
+                info!("ORPHAN: {}", block.hash());
+                emit_event!(event_bus, NodeEvent::OrphanDetected { hash: block.hash().to_string() });
+            }
 
             if let Some(engine) = adaptive_engine.as_mut() {
                 engine.update_block(&block, is_orphan).await?;
@@ -67,9 +195,26 @@ async fn main() -> Result<()> {
 
             let now = Utc::now().timestamp();
 
-            if let Some((weak_idx, tips)) = rolling_dag.find_fracture(200) {
+            let approx_samples = cfg.approx_centrality.then_some(cfg.centrality_samples);
+            let fracture = if diversity {
+                rolling_dag.find_fracture_diverse(200, approx_samples, cfg.diversity_top_n, &mut fracture_rng)
+            } else if let Some(samples) = approx_samples {
+                rolling_dag.find_fracture_approx(200, samples)
+            } else {
+                rolling_dag.find_fracture(200)
+            };
+            if let Some((weak_idx, tips, _betweenness, merge_depth)) = fracture {
                 let weak = &rolling_dag.graph[weak_idx];
-                let tip_hashes: Vec<String> = tips.iter().map(|&i| rolling_dag.graph[i].hash.clone()).collect();
+                let mut tip_hashes: Vec<String> = tips.iter().map(|&i| rolling_dag.graph[i].hash.clone()).collect();
+                // In diversity mode, advertise a randomized subset of the tips (at
+                // least two, so the stitch still bridges the fracture) so instances
+                // propose different tip combinations instead of the identical full
+                // set — reordering alone is invisible to the set-valued heal check.
+                if diversity && tip_hashes.len() > 2 {
+                    fracture_rng.shuffle(&mut tip_hashes);
+                    let keep = 2 + (fracture_rng.next_u64() as usize % (tip_hashes.len() - 1));
+                    tip_hashes.truncate(keep);
+                }
                 let blue_delta = tips.iter()
                     .map(|&i| rolling_dag.graph[i].blue_score.saturating_sub(weak.blue_score))
                     .max()
@@ -80,36 +225,53 @@ async fn main() -> Result<()> {
                 let reward = adaptive_engine.as_ref().map(|e| e.reward(sus)).unwrap_or(cfg.base_reward_sompi);
 
                 info!(
-                    "Fracture: {} | delta={} | SUS={:.2} | reward={} | stitch={} | orphan_rate={:.3}%",
-                    weak.hash, blue_delta, sus, reward, should_stitch,
+                    "Fracture: {} | delta={} | depth={} | SUS={:.2} | reward={} | stitch={} | orphan_rate={:.3}%",
+                    weak.hash, blue_delta, merge_depth, sus, reward, should_stitch,
                     adaptive_engine.as_ref().map(|e| e.orphan_rate() * 100.0).unwrap_or(0.0)
                 );
+                emit_event!(event_bus, NodeEvent::FractureFound {
+                    weak_hash: weak.hash.clone(),
+                    delta: blue_delta,
+                    betweenness: _betweenness,
+                    merge_depth,
+                });
 
                 if should_stitch {
                     p2p_stitch::broadcast_stitch(&p2p_adaptor, &weak.hash, &tip_hashes, reward, &sk).await?;
                     info!("STITCHED → {} sompi", reward);
+                    emit_event!(event_bus, NodeEvent::StitchBroadcast {
+                        reward,
+                        tips: tip_hashes.clone(),
+                    });
 
                     let tip_set: HashSet<String> = tip_hashes.iter().cloned().collect();
-                    let wallet_clone = wallet.clone();
-                    let rpc_clone = rpc_http.clone();
                     let block_hash = block.hash();
-                    tokio::spawn(async move {
-                        for _ in 0..30 {
-                            tokio::time::sleep(std::time::Duration::from_secs(HEAL_CHECK_INTERVAL_SECS)).await;
-                            if let Ok(new_block) = rpc_clone.get_block(&block_hash).await {
-                                let parents: HashSet<String> = new_block.header.direct_parents.iter().map(|h| h.to_string()).collect();
-                                if tip_set.is_subset(&parents) {
-                                    if let Some(addr) = get_miner_address(&new_block) {
-                                        match send_reward(&wallet_clone, addr, reward).await {
-                                            Ok(txid) => info!("HEALED: {}", txid),
-                                            Err(e) => warn!("Failed to send reward: {:?}", e),
-                                        }
-                                        return;
-                                    }
-                                }
-                            }
-                        }
+
+                    // Persist the outstanding stitch before spawning its watcher,
+                    // so a crash mid-heal resumes the check instead of dropping it.
+                    rolling_dag.register_stitch(dag::PendingStitch {
+                        block_hash: block_hash.to_string(),
+                        tips: tip_hashes.clone(),
+                        reward,
+                        heal_iterations: HEAL_CHECK_ITERATIONS,
                     });
+                    if let Some(path) = cfg.snapshot_path.as_deref() {
+                        if let Err(e) = rolling_dag.save_snapshot(path) {
+                            warn!("Failed to save snapshot: {:?}", e);
+                        }
+                    }
+
+                    spawn_heal_watcher(
+                        rpc_cache.clone(),
+                        wallet.clone(),
+                        event_bus.clone(),
+                        heal_done_tx.clone(),
+                        settled_path.clone(),
+                        block_hash,
+                        tip_set,
+                        reward,
+                        HEAL_CHECK_ITERATIONS,
+                    );
 
                     if let Some(engine) = adaptive_engine.as_mut() {
                         engine.record_stitch();
@@ -122,6 +284,65 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Spawns the background task that polls for a stitched block to re-merge and,
+/// once its tip set is confirmed, pays the miner reward. Shared by the live
+/// stitch path and restart recovery; `iterations` is the poll budget. A resumed
+/// watcher gets the budget stored with its stitch, which is never decremented,
+/// so it starts over with the full budget.
+///
+/// On every terminal outcome — reward paid, reward submission failed, or the
+/// budget exhausted without a confirmation — the watcher sends its `block_hash` over `done` so the main
+/// loop can drop the stitch from the snapshot and keep resume idempotent. The
+/// payout itself is guarded by a durable settled marker written to
+/// `settled_path` *before* the transaction is submitted, so a crash mid-submit
+/// can't trigger a second payment on resume.
+fn spawn_heal_watcher(
+    rpc: Arc<cache::RpcCache>,
+    wallet: kaspa_wallet_core::wallet::Wallet<InMemoryStorage>,
+    _bus: EventBus,
+    done: tokio::sync::mpsc::UnboundedSender<String>,
+    settled_path: Option<String>,
+    block_hash: kaspa_hashes::Hash,
+    tip_set: HashSet<String>,
+    reward: u64,
+    iterations: u32,
+) {
+    tokio::spawn(async move {
+        for _ in 0..iterations {
+            tokio::time::sleep(std::time::Duration::from_secs(HEAL_CHECK_INTERVAL_SECS)).await;
+            if let Ok(new_block) = rpc.get_block(&block_hash).await {
+                let parents: HashSet<String> = new_block.header.direct_parents.iter().map(|h| h.to_string()).collect();
+                if tip_set.is_subset(&parents) {
+                    if let Some(addr) = rpc.miner_address(&new_block) {
+                        // Record the payout durably before submitting it; on a
+                        // crash after this point resume skips the stitch rather
+                        // than paying again.
+                        if let Some(sp) = &settled_path {
+                            if let Err(e) = storage::BlockStorage::mark_settled(std::path::Path::new(sp), &block_hash.to_string()) {
+                                warn!("Failed to persist settled marker: {:?}", e);
+                            }
+                        }
+                        match send_reward(&wallet, addr, reward).await {
+                            Ok(txid) => {
+                                info!("HEALED: {}", txid);
+                                emit_event!(_bus, NodeEvent::HealConfirmed { txid });
+                            }
+                            Err(e) => {
+                                warn!("Failed to send reward: {:?}", e);
+                                emit_event!(_bus, NodeEvent::RewardFailed);
+                            }
+                        }
+                        let _ = done.send(block_hash.to_string());
+                        return;
+                    }
+                }
+            }
+        }
+        // Gave up without a confirmation; clear the stitch so it isn't resumed forever.
+        let _ = done.send(block_hash.to_string());
+    });
+}
+
 /// Attempts to extract the miner address from the first transaction's first output of a block.
 fn get_miner_address(block: &kaspa_consensus_core::block::Block) -> Option<Address> {
     block.transactions.first()
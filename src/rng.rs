@@ -0,0 +1,87 @@
+//! A tiny seeded PRNG used for diversity mode, so different stitchbot instances
+//! pick different weak points instead of deterministically dog-piling one.
+//!
+//! This is a SplitMix64 generator: fast, allocation-free, and fully reproducible
+//! from a configured seed, which is all the diversity sampling needs.
+
+/// Deterministic SplitMix64 pseudo-random generator.
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Creates a generator from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Advances the generator and returns the next 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniform float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Fisher-Yates shuffle of `slice` in place.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            slice.swap(i, j);
+        }
+    }
+
+    /// Picks an index into `weights` with probability proportional to each
+    /// weight; `None` if no weight is positive.
+    pub fn weighted_index(&mut self, weights: &[f64]) -> Option<usize> {
+        let total: f64 = weights.iter().copied().filter(|w| *w > 0.0).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut pick = self.next_f64() * total;
+        for (i, &w) in weights.iter().enumerate() {
+            if w <= 0.0 {
+                continue;
+            }
+            pick -= w;
+            if pick <= 0.0 {
+                return Some(i);
+            }
+        }
+        Some(weights.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_is_reproducible() {
+        let mut a = SeededRng::new(1234);
+        let mut b = SeededRng::new(1234);
+        let seq_a: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        let seq_b: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+        assert_eq!(seq_a, seq_b);
+
+        let mut c = SeededRng::new(5678);
+        let seq_c: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+        assert_ne!(seq_a, seq_c);
+    }
+
+    #[test]
+    fn weighted_index_skips_zero_weights() {
+        let mut rng = SeededRng::new(42);
+        // Only index 2 carries weight, so it must always be chosen.
+        for _ in 0..32 {
+            assert_eq!(rng.weighted_index(&[0.0, 0.0, 1.0, 0.0]), Some(2));
+        }
+        assert_eq!(rng.weighted_index(&[0.0, 0.0]), None);
+    }
+}
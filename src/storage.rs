@@ -0,0 +1,232 @@
+//! On-disk persistence for [`RollingDag`](crate::dag::RollingDag).
+//!
+//! The snapshot is a single file of length-prefixed records: the `BlockInfo`
+//! nodes in insertion order, followed by any pending stitch-healing watchers
+//! that had not yet confirmed. The format is self-describing enough to reload
+//! the rolling window and resume outstanding heal checks after a crash; the
+//! window's order is implied by the order the blocks are written in, so it is
+//! not stored separately.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::dag::{BlockInfo, PendingStitch};
+
+/// Magic bytes + format version guarding against reading a foreign file.
+const MAGIC: &[u8; 4] = b"SBDG";
+const VERSION: u8 = 2;
+
+/// The serializable state of a [`RollingDag`](crate::dag::RollingDag).
+///
+/// `blocks` is stored in insertion order, which is all the restore path needs:
+/// replaying it rebuilds the window's order record from scratch.
+pub struct Snapshot {
+    pub blocks: Vec<BlockInfo>,
+    pub pending: Vec<PendingStitch>,
+}
+
+/// Reads and writes [`Snapshot`]s using a compact length-prefixed encoding.
+pub struct BlockStorage;
+
+impl BlockStorage {
+    /// Writes `snapshot` to `path`, overwriting any existing file.
+    pub fn save(path: &Path, snapshot: &Snapshot) -> Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+
+        write_u32(&mut w, snapshot.blocks.len() as u32)?;
+        for info in &snapshot.blocks {
+            write_block(&mut w, info)?;
+        }
+
+        write_u32(&mut w, snapshot.pending.len() as u32)?;
+        for stitch in &snapshot.pending {
+            write_str(&mut w, &stitch.block_hash)?;
+            write_u32(&mut w, stitch.tips.len() as u32)?;
+            for tip in &stitch.tips {
+                write_str(&mut w, tip)?;
+            }
+            write_u64(&mut w, stitch.reward)?;
+            write_u32(&mut w, stitch.heal_iterations)?;
+        }
+
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Appends `block_hash` to the newline-delimited settled-stitch log at
+    /// `path`, flushing before returning. Written *before* a reward is submitted
+    /// so a crash between the write and the payout skips the stitch on resume
+    /// instead of paying the same miner twice.
+    pub fn mark_settled(path: &Path, block_hash: &str) -> Result<()> {
+        use std::fs::OpenOptions;
+        let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(f, "{}", block_hash)?;
+        f.flush()?;
+        Ok(())
+    }
+
+    /// Loads the set of stitch block hashes already marked settled, or an empty
+    /// set when the log does not exist yet.
+    pub fn load_settled(path: &Path) -> std::collections::HashSet<String> {
+        std::fs::read_to_string(path)
+            .map(|s| {
+                s.lines()
+                    .map(|l| l.trim().to_string())
+                    .filter(|l| !l.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reads a [`Snapshot`] previously written by [`save`](BlockStorage::save).
+    pub fn load(path: &Path) -> Result<Snapshot> {
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a stitchbot snapshot");
+        }
+        let mut version = [0u8; 1];
+        r.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            bail!("unsupported snapshot version {}", version[0]);
+        }
+
+        let block_count = read_u32(&mut r)?;
+        let mut blocks = Vec::with_capacity(block_count as usize);
+        for _ in 0..block_count {
+            blocks.push(read_block(&mut r)?);
+        }
+
+        let pending_count = read_u32(&mut r)?;
+        let mut pending = Vec::with_capacity(pending_count as usize);
+        for _ in 0..pending_count {
+            let block_hash = read_str(&mut r)?;
+            let tip_count = read_u32(&mut r)?;
+            let mut tips = Vec::with_capacity(tip_count as usize);
+            for _ in 0..tip_count {
+                tips.push(read_str(&mut r)?);
+            }
+            let reward = read_u64(&mut r)?;
+            let heal_iterations = read_u32(&mut r)?;
+            pending.push(PendingStitch { block_hash, tips, reward, heal_iterations });
+        }
+
+        Ok(Snapshot { blocks, pending })
+    }
+}
+
+fn write_block<W: Write>(w: &mut W, info: &BlockInfo) -> io::Result<()> {
+    write_str(w, &info.hash)?;
+    write_u64(w, info.blue_score)?;
+    write_u32(w, info.parents.len() as u32)?;
+    for parent in &info.parents {
+        write_str(w, parent)?;
+    }
+    write_u64(w, info.timestamp)
+}
+
+fn read_block<R: Read>(r: &mut R) -> Result<BlockInfo> {
+    let hash = read_str(r)?;
+    let blue_score = read_u64(r)?;
+    let parent_count = read_u32(r)?;
+    let mut parents = Vec::with_capacity(parent_count as usize);
+    for _ in 0..parent_count {
+        parents.push(read_str(r)?);
+    }
+    let timestamp = read_u64(r)?;
+    Ok(BlockInfo { hash, blue_score, parents, timestamp })
+}
+
+fn write_str<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    write_u32(w, s.len() as u32)?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_str<R: Read>(r: &mut R) -> Result<String> {
+    let len = read_u32(r)? as usize;
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+fn write_u32<W: Write>(w: &mut W, v: u32) -> io::Result<()> {
+    w.write_all(&v.to_be_bytes())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn write_u64<W: Write>(w: &mut W, v: u64) -> io::Result<()> {
+    w.write_all(&v.to_be_bytes())
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unique scratch path under the temp dir for a single test.
+    fn temp_path(tag: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("sb_{}_{}", tag, std::process::id()))
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let snapshot = Snapshot {
+            blocks: vec![
+                BlockInfo { hash: "a".into(), blue_score: 1, parents: vec![], timestamp: 10 },
+                BlockInfo { hash: "b".into(), blue_score: 2, parents: vec!["a".into()], timestamp: 20 },
+            ],
+            pending: vec![PendingStitch {
+                block_hash: "b".into(),
+                tips: vec!["a".into(), "x".into()],
+                reward: 42,
+                heal_iterations: 7,
+            }],
+        };
+
+        let path = temp_path("snap");
+        BlockStorage::save(&path, &snapshot).unwrap();
+        let loaded = BlockStorage::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.blocks.len(), 2);
+        assert_eq!(loaded.blocks[1].hash, "b");
+        assert_eq!(loaded.blocks[1].parents, vec!["a".to_string()]);
+        assert_eq!(loaded.pending.len(), 1);
+        assert_eq!(loaded.pending[0].tips, vec!["a".to_string(), "x".to_string()]);
+        assert_eq!(loaded.pending[0].reward, 42);
+        assert_eq!(loaded.pending[0].heal_iterations, 7);
+    }
+
+    #[test]
+    fn settled_log_accumulates() {
+        let path = temp_path("settled");
+        let _ = std::fs::remove_file(&path);
+
+        assert!(BlockStorage::load_settled(&path).is_empty());
+        BlockStorage::mark_settled(&path, "h1").unwrap();
+        BlockStorage::mark_settled(&path, "h2").unwrap();
+        let settled = BlockStorage::load_settled(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(settled.len(), 2);
+        assert!(settled.contains("h1"));
+        assert!(settled.contains("h2"));
+    }
+}